@@ -0,0 +1,26 @@
+/// Defines a set that may allow "some" or "all" members, where "some" carries the actual set of
+/// permitted values (origins, headers, etc.).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AllOrSome<T> {
+    All,
+    Some(T),
+}
+
+impl<T> Default for AllOrSome<T> {
+    fn default() -> Self {
+        AllOrSome::All
+    }
+}
+
+impl<T> AllOrSome<T> {
+    /// Returns whether this wrapper allows all values.
+    pub(crate) fn is_all(&self) -> bool {
+        matches!(*self, AllOrSome::All)
+    }
+
+    /// Returns whether this wrapper allows some values.
+    #[allow(dead_code)]
+    pub(crate) fn is_some(&self) -> bool {
+        !self.is_all()
+    }
+}
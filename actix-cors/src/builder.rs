@@ -0,0 +1,651 @@
+use std::{
+    collections::HashSet,
+    convert::{TryFrom, TryInto},
+    fmt,
+    iter::FromIterator as _,
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::RequestHead,
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method,
+    },
+    HttpResponse,
+};
+use regex::Regex;
+
+use crate::{AllOrSome, CorsError};
+
+/// A closure, added via `allowed_origin_fn`, that decides whether a request origin is allowed.
+pub(crate) type OriginFn = Rc<dyn Fn(&HeaderValue, &RequestHead) -> bool>;
+
+/// A closure that builds a custom response for a request rejected by CORS validation.
+pub(crate) type RejectionHandler = Rc<dyn Fn(&CorsError, &RequestHead) -> HttpResponse>;
+
+/// Internal, baked settings used by [`CorsMiddleware`](crate::middleware::CorsMiddleware) to
+/// service requests.
+///
+/// Held by [`Cors`]; every middleware instance it creates gets its own baked copy behind an
+/// `Rc`.
+#[derive(Clone)]
+pub(crate) struct Inner {
+    pub(crate) allowed_origins: AllOrSome<HashSet<HeaderValue>>,
+    /// Whether `allowed_origins` was explicitly assigned (via [`Cors::allow_any_origin`] or
+    /// [`Cors::allowed_origin`] with an exact origin; a `*` pattern does not count) rather than
+    /// left at its `Default` value. `Default` seeds
+    /// `allowed_origins` with an empty `Some` set (deny all) for a standalone policy, which is
+    /// indistinguishable from an *explicit* empty set unless tracked separately; [`Inner::merge`]
+    /// needs the distinction to tell "child narrows origins to nothing" apart from "child didn't
+    /// touch origins, inherit the parent's".
+    pub(crate) allowed_origins_configured: bool,
+    pub(crate) allowed_origin_patterns: Vec<Regex>,
+    pub(crate) allowed_origins_fns: Vec<OriginFn>,
+
+    pub(crate) allowed_methods: HashSet<Method>,
+    pub(crate) allowed_methods_baked: Option<HeaderValue>,
+
+    pub(crate) allowed_headers: AllOrSome<HashSet<HeaderName>>,
+    pub(crate) allowed_headers_baked: Option<HeaderValue>,
+
+    pub(crate) expose_headers: AllOrSome<HashSet<HeaderName>>,
+    pub(crate) expose_headers_baked: Option<HeaderValue>,
+
+    pub(crate) max_age: Option<usize>,
+    pub(crate) preflight: bool,
+    pub(crate) send_wildcard: bool,
+    pub(crate) supports_credentials: bool,
+    pub(crate) vary_header: bool,
+
+    pub(crate) rejection_handler: Option<RejectionHandler>,
+}
+
+// manual impl since `OriginFn`/`RejectionHandler` (`Rc<dyn Fn(..)>`) don't implement `Debug`
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("allowed_origins", &self.allowed_origins)
+            .field("allowed_origins_configured", &self.allowed_origins_configured)
+            .field("allowed_origin_patterns", &self.allowed_origin_patterns)
+            .field("allowed_origins_fns", &self.allowed_origins_fns.len())
+            .field("allowed_methods", &self.allowed_methods)
+            .field("allowed_headers", &self.allowed_headers)
+            .field("expose_headers", &self.expose_headers)
+            .field("max_age", &self.max_age)
+            .field("preflight", &self.preflight)
+            .field("send_wildcard", &self.send_wildcard)
+            .field("supports_credentials", &self.supports_credentials)
+            .field("vary_header", &self.vary_header)
+            .field("rejection_handler", &self.rejection_handler.is_some())
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Bakes the `allowed_methods`/`allowed_headers`/`expose_headers` sets down into their
+    /// corresponding pre-serialized `HeaderValue`s, so that preflight and actual-request
+    /// handling don't have to re-serialize them on every request.
+    ///
+    /// Must be called once the builder's sets are final, i.e. when the middleware is built.
+    pub(crate) fn bake(&mut self) {
+        self.allowed_methods_baked = (!self.allowed_methods.is_empty()).then(|| {
+            let methods = self
+                .allowed_methods
+                .iter()
+                .map(Method::as_str)
+                .collect::<HashSet<_>>();
+
+            intersperse_header_values(&methods)
+        });
+
+        self.allowed_headers_baked = match self.allowed_headers {
+            AllOrSome::All => None,
+            AllOrSome::Some(ref headers) if headers.is_empty() => None,
+            AllOrSome::Some(ref headers) => {
+                let headers = headers.iter().map(HeaderName::as_str).collect::<HashSet<_>>();
+                Some(intersperse_header_values(&headers))
+            }
+        };
+
+        self.expose_headers_baked = match self.expose_headers {
+            AllOrSome::All => None,
+            AllOrSome::Some(ref headers) if headers.is_empty() => None,
+            AllOrSome::Some(ref headers) => {
+                let headers = headers.iter().map(HeaderName::as_str).collect::<HashSet<_>>();
+                Some(intersperse_header_values(&headers))
+            }
+        };
+    }
+
+    /// Returns true if `origin` is allowed by the exact set, a compiled wildcard/regex pattern,
+    /// or by any `allowed_origin_fn`, in that order.
+    ///
+    /// When all origins are allowed but patterns or `allowed_origin_fn`s are also configured,
+    /// only those patterns and closures decide.
+    fn validate_origin_allowed(&self, origin: &HeaderValue, req: &RequestHead) -> bool {
+        let allowed = match self.allowed_origins {
+            AllOrSome::All => {
+                self.allowed_origin_patterns.is_empty() && self.allowed_origins_fns.is_empty()
+            }
+            AllOrSome::Some(ref allowed_origins) => allowed_origins.contains(origin),
+        };
+
+        if allowed {
+            return true;
+        }
+
+        if let Ok(origin_str) = origin.to_str() {
+            if self
+                .allowed_origin_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(origin_str))
+            {
+                return true;
+            }
+        }
+
+        self.allowed_origins_fns.iter().any(|f| f(origin, req))
+    }
+
+    /// Validates the `Origin` request header against the configured policy.
+    ///
+    /// A request with no `Origin` header is always allowed through; it is not a CORS request.
+    pub(crate) fn validate_origin(&self, req: &RequestHead) -> Result<(), CorsError> {
+        if self.allowed_origins.is_all()
+            && self.allowed_origin_patterns.is_empty()
+            && self.allowed_origins_fns.is_empty()
+        {
+            return Ok(());
+        }
+
+        match req.headers.get(header::ORIGIN) {
+            Some(origin) if self.validate_origin_allowed(origin, req) => Ok(()),
+            Some(_) => Err(CorsError::OriginNotAllowed),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn validate_allowed_method(&self, req: &RequestHead) -> Result<(), CorsError> {
+        if let Some(hdr) = req.headers.get(header::ACCESS_CONTROL_REQUEST_METHOD) {
+            let hdr = hdr.to_str().map_err(|_| CorsError::MethodNotAllowed)?;
+            let method = hdr.parse().map_err(|_| CorsError::MethodNotAllowed)?;
+            self.allowed_methods
+                .get(&method)
+                .map(|_| ())
+                .ok_or(CorsError::MethodNotAllowed)
+        } else {
+            Err(CorsError::MethodNotAllowed)
+        }
+    }
+
+    pub(crate) fn validate_allowed_headers(&self, req: &RequestHead) -> Result<(), CorsError> {
+        match self.allowed_headers {
+            AllOrSome::All => Ok(()),
+            AllOrSome::Some(ref allowed_headers) => {
+                match req.headers.get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+                    Some(hdr) => {
+                        let hdr_str = hdr.to_str().map_err(|_| CorsError::HeadersNotAllowed)?;
+                        let requested = hdr_str.split(',').map(|h| h.trim());
+
+                        for hdr in requested {
+                            match hdr.parse::<HeaderName>() {
+                                Ok(hdr) if allowed_headers.contains(&hdr) => continue,
+                                _ => return Err(CorsError::HeadersNotAllowed),
+                            }
+                        }
+
+                        Ok(())
+                    }
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Determines the value, if any, that should be echoed back in the
+    /// `Access-Control-Allow-Origin` response header for this request.
+    pub(crate) fn access_control_allow_origin(&self, req: &RequestHead) -> Option<HeaderValue> {
+        let origin = req.headers.get(header::ORIGIN)?;
+
+        match self.allowed_origins {
+            AllOrSome::All => {
+                if self.send_wildcard_enabled() {
+                    Some(HeaderValue::from_static("*"))
+                } else {
+                    Some(origin.clone())
+                }
+            }
+            AllOrSome::Some(_) => self
+                .validate_origin_allowed(origin, req)
+                .then(|| origin.clone()),
+        }
+    }
+
+    /// Returns true when a literal `*` should be sent in place of reflecting the request's
+    /// origin, i.e. `send_wildcard` was set, all origins are allowed, and credentials are not
+    /// supported (the two are mutually exclusive and rejected at finalization time).
+    pub(crate) fn send_wildcard_enabled(&self) -> bool {
+        self.send_wildcard && !self.supports_credentials && self.allowed_origins.is_all()
+    }
+
+    /// Validates invariants that can only be checked once a policy is finalized (i.e. turned
+    /// into a [`CorsMiddleware`](crate::middleware::CorsMiddleware) or [`CorsOverride`]), as
+    /// opposed to at the point an individual builder method is called.
+    ///
+    /// Shared by `Cors`'s `Transform::new_transform` impl and [`Cors::into_override`], so the
+    /// invariant holds everywhere a [`CorsError`] can originate.
+    pub(crate) fn validate_finalize(&self) -> Result<(), CorsError> {
+        if self.send_wildcard && self.supports_credentials {
+            return Err(CorsError::WildcardOriginWithCredentials);
+        }
+
+        Ok(())
+    }
+
+    /// Layers `self` (the policy this middleware was configured with) with a per-request
+    /// `child` policy, e.g. one installed in request extensions by a route-level
+    /// guard or handler.
+    ///
+    /// Allowed methods and headers are unioned, since either policy permitting a method/header
+    /// should be enough to satisfy a preflight check. Allowed origins are overridden: if `child`
+    /// explicitly set its own origins (`allowed_origins_configured`), they replace `self`'s
+    /// entirely, so a route can narrow (or widen) which origins it accepts without fighting the
+    /// enclosing policy; otherwise `self`'s origins carry through unchanged, since an override
+    /// that never touched origins (e.g. `Cors::default()`, whose untouched `allowed_origins` is
+    /// an empty set) must not be read as "deny every origin". The two policies'
+    /// `allowed_origin_fn`/regex patterns are combined rather than replaced, since those are
+    /// typically additive conditions.
+    pub(crate) fn merge(&self, child: &Inner) -> Inner {
+        let allowed_headers = match (&self.allowed_headers, &child.allowed_headers) {
+            (AllOrSome::All, _) | (_, AllOrSome::All) => AllOrSome::All,
+            (AllOrSome::Some(a), AllOrSome::Some(b)) => {
+                AllOrSome::Some(a.union(b).cloned().collect())
+            }
+        };
+
+        let expose_headers = match (&self.expose_headers, &child.expose_headers) {
+            (AllOrSome::All, _) | (_, AllOrSome::All) => AllOrSome::All,
+            (AllOrSome::Some(a), AllOrSome::Some(b)) => {
+                AllOrSome::Some(a.union(b).cloned().collect())
+            }
+        };
+
+        Inner {
+            allowed_origins: if child.allowed_origins_configured {
+                child.allowed_origins.clone()
+            } else {
+                self.allowed_origins.clone()
+            },
+            allowed_origins_configured: self.allowed_origins_configured
+                || child.allowed_origins_configured,
+            allowed_origin_patterns: self
+                .allowed_origin_patterns
+                .iter()
+                .chain(child.allowed_origin_patterns.iter())
+                .cloned()
+                .collect(),
+            allowed_origins_fns: self
+                .allowed_origins_fns
+                .iter()
+                .chain(child.allowed_origins_fns.iter())
+                .cloned()
+                .collect(),
+
+            allowed_methods: self
+                .allowed_methods
+                .union(&child.allowed_methods)
+                .cloned()
+                .collect(),
+            allowed_methods_baked: None,
+
+            allowed_headers,
+            allowed_headers_baked: None,
+
+            expose_headers,
+            expose_headers_baked: None,
+
+            max_age: child.max_age.or(self.max_age),
+            preflight: self.preflight,
+            // `self` and `child` each individually satisfy `validate_finalize`'s invariant (both
+            // went through it via `new_transform`/`into_override`), but OR-ing their flags can
+            // still produce a merged policy with both `send_wildcard` and `supports_credentials`
+            // set (e.g. a credentialed parent merged with a `send_wildcard` override) — `merge`
+            // deliberately doesn't re-run `validate_finalize` and surface a `CorsError` for that,
+            // since `access_control_allow_origin`'s use of `send_wildcard_enabled()` already
+            // refuses to emit a literal `*` whenever `supports_credentials` is set, so the
+            // merged policy can never actually produce the illegal combination in a response.
+            send_wildcard: child.send_wildcard || self.send_wildcard,
+            supports_credentials: child.supports_credentials || self.supports_credentials,
+            vary_header: self.vary_header,
+            rejection_handler: child
+                .rejection_handler
+                .clone()
+                .or_else(|| self.rejection_handler.clone()),
+        }
+    }
+}
+
+/// Builder for CORS middleware.
+///
+/// To construct a CORS middleware, call [`Cors::default()`] to construct a restrictive default
+/// configuration, then chain configuration methods before passing it to [`App::wrap`].
+///
+/// [`App::wrap`]: actix_web::App::wrap
+#[derive(Debug)]
+pub struct Cors {
+    pub(crate) inner: Result<Inner, CorsError>,
+}
+
+impl Cors {
+    /// A very permissive set of default for quick prototyping.
+    ///
+    /// Originates, methods, and headers are all allowed. Note this is the least secure
+    /// configuration and should not be used in production.
+    pub fn permissive() -> Self {
+        let inner = Inner {
+            allowed_origins: AllOrSome::All,
+            allowed_origins_configured: true,
+            allowed_origin_patterns: Vec::new(),
+            allowed_origins_fns: Vec::new(),
+            allowed_methods: HashSet::from_iter(vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PATCH,
+            ]),
+            allowed_methods_baked: None,
+            allowed_headers: AllOrSome::All,
+            allowed_headers_baked: None,
+            expose_headers: AllOrSome::All,
+            expose_headers_baked: None,
+            max_age: None,
+            preflight: true,
+            send_wildcard: false,
+            supports_credentials: false,
+            vary_header: true,
+            rejection_handler: None,
+        };
+
+        Cors { inner: Ok(inner) }
+    }
+
+    fn inner_mut(&mut self) -> Option<&mut Inner> {
+        self.inner.as_mut().ok()
+    }
+
+    fn error(&mut self, err: CorsError) {
+        self.inner = Err(err);
+    }
+
+    /// Resets allowed origins to allow any origin.
+    pub fn allow_any_origin(mut self) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.allowed_origins = AllOrSome::All;
+            inner.allowed_origins_configured = true;
+        }
+
+        self
+    }
+
+    /// Adds an origin to the set of allowed origins.
+    ///
+    /// A `*` anywhere in `origin` is treated as a wildcard shorthand (e.g.
+    /// `"https://*.example.com"`) and compiled into a regex pattern rather than being added to
+    /// the exact-match set; use [`allowed_origin_regex`](Self::allowed_origin_regex) directly if
+    /// you need full regex syntax.
+    pub fn allowed_origin(mut self, origin: &str) -> Cors {
+        if origin.contains('*') {
+            let pattern = glob_to_regex(origin);
+            return self.allowed_origin_regex(&pattern);
+        }
+
+        match TryInto::<HeaderValue>::try_into(origin) {
+            Ok(origin) => {
+                if let Some(inner) = self.inner_mut() {
+                    if let AllOrSome::Some(ref mut origins) = inner.allowed_origins {
+                        origins.insert(origin);
+                    } else {
+                        inner.allowed_origins = AllOrSome::Some(HashSet::from_iter(vec![origin]));
+                    }
+                    inner.allowed_origins_configured = true;
+                }
+            }
+            Err(_) => self.error(CorsError::ParseOrigin),
+        }
+
+        self
+    }
+
+    /// Adds a regex pattern that request origins are matched against.
+    ///
+    /// The pattern is searched for anywhere in the `Origin` header value (e.g.
+    /// `"https://sub.example.com"`); anchor with `^`/`$` to match the whole value.
+    ///
+    /// The echoed `Access-Control-Allow-Origin` is always the literal request origin, never the
+    /// pattern itself.
+    pub fn allowed_origin_regex(mut self, pattern: &str) -> Cors {
+        match Regex::new(pattern) {
+            Ok(pattern) => {
+                if let Some(inner) = self.inner_mut() {
+                    inner.allowed_origin_patterns.push(pattern);
+                }
+            }
+            Err(_) => self.error(CorsError::ParseOrigin),
+        }
+
+        self
+    }
+
+    /// Adds a closure that is run against every request's `Origin` header to determine whether
+    /// it should be allowed or not.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Cors
+    where
+        F: (Fn(&HeaderValue, &RequestHead) -> bool) + 'static,
+    {
+        if let Some(inner) = self.inner_mut() {
+            inner.allowed_origins_fns.push(Rc::new(f));
+        }
+
+        self
+    }
+
+    /// Sets allowed methods.
+    pub fn allowed_methods<U, M>(mut self, methods: U) -> Cors
+    where
+        U: IntoIterator<Item = M>,
+        Method: TryFrom<M>,
+    {
+        if let Some(inner) = self.inner_mut() {
+            for m in methods {
+                match Method::try_from(m) {
+                    Ok(method) => {
+                        inner.allowed_methods.insert(method);
+                    }
+                    Err(_) => {
+                        self.error(CorsError::ParseOrigin);
+                        return self;
+                    }
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Sets the `Access-Control-Max-Age` header for preflight requests, in seconds.
+    pub fn max_age(mut self, max_age: usize) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.max_age = Some(max_age);
+        }
+
+        self
+    }
+
+    /// Allows credentialed requests (cookies, HTTP auth) to be made.
+    pub fn supports_credentials(mut self) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.supports_credentials = true;
+        }
+
+        self
+    }
+
+    /// When all origins are allowed (via [`allow_any_origin`](Self::allow_any_origin)) and
+    /// credentials are not supported, echo back a literal `*` in `Access-Control-Allow-Origin`
+    /// instead of reflecting the request's `Origin` header.
+    ///
+    /// This allows the response to be shared-cached, since it no longer varies per origin. It is
+    /// invalid to combine this with [`supports_credentials`](Self::supports_credentials), since
+    /// `*` is not a legal `Access-Control-Allow-Origin` value for credentialed requests; doing so
+    /// is rejected at finalization time with [`CorsError::WildcardOriginWithCredentials`].
+    pub fn send_wildcard(mut self) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.send_wildcard = true;
+        }
+
+        self
+    }
+
+    /// Sets a custom handler for building the response to a request rejected by origin, method,
+    /// or header validation.
+    ///
+    /// By default, a rejected request gets the generic `CorsError` response (HTTP 400). Set this
+    /// to return a different status, body, or error envelope that matches the rest of your API.
+    pub fn rejection_handler<F>(mut self, f: F) -> Cors
+    where
+        F: Fn(&CorsError, &RequestHead) -> HttpResponse + 'static,
+    {
+        if let Some(inner) = self.inner_mut() {
+            inner.rejection_handler = Some(Rc::new(f));
+        }
+
+        self
+    }
+
+    /// Disables the `Vary` header from being added to responses (`Origin` on actual responses,
+    /// and the request-describing header names on preflight responses).
+    pub fn disable_vary_header(mut self) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.vary_header = false;
+        }
+
+        self
+    }
+
+    /// Disables automatic `OPTIONS` preflight handling.
+    pub fn disable_preflight(mut self) -> Cors {
+        if let Some(inner) = self.inner_mut() {
+            inner.preflight = false;
+        }
+
+        self
+    }
+
+    /// Derives a new builder, seeded with this one's current settings, that can be narrowed
+    /// (or widened) further before being turned into a per-route [`CorsOverride`].
+    ///
+    /// This is the starting point for layering a broad `App`-level [`Cors`] with a stricter
+    /// policy for a specific `Scope` or `Resource`; see [`into_override`](Self::into_override).
+    pub fn extend(&self) -> Cors {
+        Cors {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Finalizes this builder into a [`CorsOverride`] that can be inserted into a request's
+    /// extensions (e.g. from a guard or handler) to narrow or widen the policy an enclosing
+    /// `Cors` middleware applies to that one request.
+    ///
+    /// Unlike wrapping a service in another `Cors` middleware, an override is merged with the
+    /// enclosing policy rather than replacing it outright: allowed methods and headers are
+    /// unioned, while allowed origins set here take precedence. See [`Inner::merge`].
+    pub fn into_override(self) -> Result<CorsOverride, CorsError> {
+        let inner = self.inner?;
+        inner.validate_finalize()?;
+        Ok(CorsOverride(Rc::new(inner)))
+    }
+}
+
+/// A per-request override of an enclosing [`Cors`] middleware's policy.
+///
+/// Insert one into a request's extensions (`req.extensions_mut().insert(..)`) from a guard or
+/// handler to narrow (or widen) the CORS policy applied to that specific request, without
+/// affecting any other route served by the same middleware. Build one with
+/// [`Cors::into_override`].
+#[derive(Debug, Clone)]
+pub struct CorsOverride(pub(crate) Rc<Inner>);
+
+impl Default for Cors {
+    /// A restrictive default set of CORS settings.
+    ///
+    /// No origins, methods, or headers are allowed; callers must explicitly opt each one in.
+    ///
+    /// The empty `allowed_origins` set here is *not* treated as an explicit "deny all origins"
+    /// when this builder is turned into a [`CorsOverride`] and merged with an enclosing policy
+    /// (see [`Inner::merge`]) — only [`allow_any_origin`](Cors::allow_any_origin) or
+    /// [`allowed_origin`](Cors::allowed_origin) with an exact (non-`*`) origin mark origins as
+    /// explicitly configured.
+    fn default() -> Self {
+        let inner = Inner {
+            allowed_origins: AllOrSome::Some(HashSet::new()),
+            allowed_origins_configured: false,
+            allowed_origin_patterns: Vec::new(),
+            allowed_origins_fns: Vec::new(),
+            allowed_methods: HashSet::new(),
+            allowed_methods_baked: None,
+            allowed_headers: AllOrSome::Some(HashSet::new()),
+            allowed_headers_baked: None,
+            expose_headers: AllOrSome::Some(HashSet::new()),
+            expose_headers_baked: None,
+            max_age: None,
+            preflight: true,
+            send_wildcard: false,
+            supports_credentials: false,
+            vary_header: true,
+            rejection_handler: None,
+        };
+
+        Cors { inner: Ok(inner) }
+    }
+}
+
+/// Converts a `*`-wildcard origin shorthand (e.g. `"https://*.example.com"`) into an anchored
+/// regex pattern matching the whole `Origin` header value.
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::with_capacity(glob.len() + 2);
+    pattern.push('^');
+
+    for (i, part) in glob.split('*').enumerate() {
+        if i > 0 {
+            pattern.push_str(".*");
+        }
+
+        pattern.push_str(&regex::escape(part));
+    }
+
+    pattern.push('$');
+    pattern
+}
+
+/// Combines a set of header names into a comma-separated `HeaderValue`.
+///
+/// Panics if `headers` is empty.
+pub(crate) fn intersperse_header_values(headers: &HashSet<&str>) -> HeaderValue {
+    assert!(!headers.is_empty(), "header name set must not be empty");
+
+    let mut value = String::with_capacity(headers.len() * 10);
+
+    for (i, h) in headers.iter().enumerate() {
+        if i > 0 {
+            value.push_str(", ");
+        }
+
+        value.push_str(h);
+    }
+
+    HeaderValue::try_from(value).expect("joined header names should always be a valid header value")
+}
@@ -0,0 +1,34 @@
+use actix_web::{http::StatusCode, ResponseError};
+use derive_more::Display;
+
+/// Errors that can occur when processing CORS guarded requests.
+#[derive(Debug, Clone, Copy, Display)]
+#[non_exhaustive]
+pub enum CorsError {
+    /// Allowed origin argument must be a valid URI or a wildcard/regex pattern.
+    #[display(fmt = "origin is not a valid URI or wildcard/regex pattern")]
+    ParseOrigin,
+
+    /// Origin is not allowed to make this request.
+    #[display(fmt = "origin is not allowed to make this request")]
+    OriginNotAllowed,
+
+    /// Requested method is not allowed.
+    #[display(fmt = "requested method is not allowed")]
+    MethodNotAllowed,
+
+    /// One or more headers requested are not allowed.
+    #[display(fmt = "one or more headers requested are not allowed")]
+    HeadersNotAllowed,
+
+    /// `send_wildcard` cannot be combined with `supports_credentials`, since `*` is not a
+    /// valid value for `Access-Control-Allow-Origin` when credentials are enabled.
+    #[display(fmt = "send_wildcard cannot be used together with supports_credentials")]
+    WildcardOriginWithCredentials,
+}
+
+impl ResponseError for CorsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
@@ -0,0 +1,66 @@
+//! Cross-Origin Resource Sharing (CORS) controls for Actix Web.
+//!
+//! # Example
+//!
+//! ```
+//! use actix_cors::Cors;
+//!
+//! let cors = Cors::default()
+//!       .allowed_origin("https://www.rust-lang.org")
+//!       .allowed_methods(vec!["GET", "POST"])
+//!       .supports_credentials()
+//!       .max_age(3600);
+//! ```
+
+#![deny(rust_2018_idioms, nonstandard_style)]
+#![warn(future_incompatible)]
+
+mod all_or_some;
+mod builder;
+mod error;
+mod middleware;
+
+use std::rc::Rc;
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use futures_util::future::{ready, Ready};
+
+pub(crate) use self::all_or_some::AllOrSome;
+pub use self::error::CorsError;
+pub(crate) use self::builder::Inner;
+pub use self::builder::{Cors, CorsOverride};
+pub use self::middleware::CorsMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: actix_web::body::MessageBody + 'static,
+    B::Error: std::error::Error,
+{
+    type Response = ServiceResponse;
+    type Error = actix_web::Error;
+    type InitError = ();
+    type Transform = CorsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let inner = match &self.inner {
+            Ok(inner) => inner.validate_finalize().map(|_| inner.clone()),
+            Err(err) => Err(*err),
+        };
+
+        let inner = match inner {
+            Ok(mut inner) => {
+                inner.bake();
+                Rc::new(inner)
+            }
+            Err(err) => {
+                log::error!("CORS middleware could not be configured: {}", err);
+                return ready(Err(()));
+            }
+        };
+
+        ready(Ok(CorsMiddleware { service, inner }))
+    }
+}
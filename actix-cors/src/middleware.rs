@@ -8,12 +8,13 @@ use actix_web::{
         header::{self, HeaderValue},
         Method,
     },
+    HttpMessage as _,
     HttpResponse,
 };
 use futures_util::future::{ok, Either, FutureExt as _, LocalBoxFuture, Ready, TryFutureExt as _};
 use log::debug;
 
-use crate::{builder::intersperse_header_values, AllOrSome, Inner};
+use crate::{builder::intersperse_header_values, AllOrSome, CorsError, CorsOverride, Inner};
 
 /// Service wrapper for Cross-Origin Resource Sharing support.
 ///
@@ -27,13 +28,40 @@ pub struct CorsMiddleware<S> {
 }
 
 impl<S> CorsMiddleware<S> {
+    /// Resolves the policy to apply to this request: `inner` as configured on this middleware,
+    /// merged with a [`CorsOverride`] found in the request's extensions, if any (see
+    /// [`Cors::into_override`](crate::Cors::into_override)).
+    fn resolve_inner(inner: &Rc<Inner>, req: &ServiceRequest) -> Rc<Inner> {
+        match req.extensions().get::<CorsOverride>() {
+            Some(over) => {
+                let mut merged = inner.merge(&over.0);
+                merged.bake();
+                Rc::new(merged)
+            }
+            None => Rc::clone(inner),
+        }
+    }
+
+    /// Builds the response for a request rejected by origin/method/header validation, deferring
+    /// to `inner.rejection_handler` when the application supplied one, and falling back to the
+    /// default `CorsError` response (HTTP 400) otherwise.
+    fn rejection_response(inner: &Inner, req: ServiceRequest, err: CorsError) -> ServiceResponse {
+        match inner.rejection_handler.as_ref() {
+            Some(handler) => {
+                let res = handler(&err, req.head());
+                req.into_response(res)
+            }
+            None => req.error_response(err),
+        }
+    }
+
     fn handle_preflight(inner: &Inner, req: ServiceRequest) -> ServiceResponse {
         if let Err(err) = inner
             .validate_origin(req.head())
             .and_then(|_| inner.validate_allowed_method(req.head()))
             .and_then(|_| inner.validate_allowed_headers(req.head()))
         {
-            return req.error_response(err);
+            return Self::rejection_response(inner, req, err);
         }
 
         let mut res = HttpResponse::Ok();
@@ -67,6 +95,17 @@ impl<S> CorsMiddleware<S> {
             res.insert_header((header::ACCESS_CONTROL_MAX_AGE, max_age.to_string()));
         }
 
+        if inner.vary_header {
+            // nothing earlier in this builder sets Vary, so there's no existing value to merge;
+            // a literal `*` allow-origin value doesn't vary per origin, so `Origin` is left out
+            let vary = if inner.send_wildcard_enabled() {
+                "Access-Control-Request-Method, Access-Control-Request-Headers"
+            } else {
+                "Origin, Access-Control-Request-Method, Access-Control-Request-Headers"
+            };
+            res.insert_header((header::VARY, HeaderValue::from_static(vary)));
+        }
+
         let res = res.finish();
         req.into_response(res)
     }
@@ -115,17 +154,11 @@ impl<S> CorsMiddleware<S> {
             );
         }
 
-        if inner.vary_header {
-            let value = match res.headers_mut().get(header::VARY) {
-                Some(hdr) => {
-                    let mut val: Vec<u8> = Vec::with_capacity(hdr.len() + 8);
-                    val.extend(hdr.as_bytes());
-                    val.extend(b", Origin");
-                    val.try_into().unwrap()
-                }
-                None => HeaderValue::from_static("Origin"),
-            };
-
+        // a literal `*` allow-origin value doesn't vary per request, so the cache-defeating
+        // `Vary: Origin` header would be actively wrong here
+        if inner.vary_header && !inner.send_wildcard_enabled() {
+            let existing = res.headers().get(header::VARY).cloned();
+            let value = append_vary(existing.as_ref(), "Origin");
             res.headers_mut().insert(header::VARY, value);
         }
 
@@ -133,6 +166,21 @@ impl<S> CorsMiddleware<S> {
     }
 }
 
+/// Appends `addition` to an existing `Vary` header value, or creates a new one if `existing`
+/// is `None`.
+fn append_vary(existing: Option<&HeaderValue>, addition: &str) -> HeaderValue {
+    match existing {
+        Some(hdr) => {
+            let mut val: Vec<u8> = Vec::with_capacity(hdr.len() + 2 + addition.len());
+            val.extend(hdr.as_bytes());
+            val.extend(b", ");
+            val.extend(addition.as_bytes());
+            val.try_into().unwrap()
+        }
+        None => addition.try_into().unwrap(),
+    }
+}
+
 type CorsMiddlewareServiceFuture = Either<
     Ready<Result<ServiceResponse, Error>>,
     LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
@@ -152,8 +200,9 @@ where
     actix_service::forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        if self.inner.preflight && req.method() == Method::OPTIONS {
-            let inner = Rc::clone(&self.inner);
+        let inner = Self::resolve_inner(&self.inner, &req);
+
+        if inner.preflight && req.method() == Method::OPTIONS {
             let res = Self::handle_preflight(&inner, req);
             Either::Left(ok(res))
         } else {
@@ -161,13 +210,12 @@ where
 
             if origin.is_some() {
                 // Only check requests with a origin header.
-                if let Err(err) = self.inner.validate_origin(req.head()) {
+                if let Err(err) = inner.validate_origin(req.head()) {
                     debug!("origin validation failed; inner service is not called");
-                    return Either::Left(ok(req.error_response(err)));
+                    return Either::Left(ok(Self::rejection_response(&inner, req, err)));
                 }
             }
 
-            let inner = Rc::clone(&self.inner);
             let fut = self.service.call(req);
 
             let res = async move {
@@ -192,6 +240,7 @@ where
 mod tests {
     use actix_web::{
         dev::Transform,
+        http::StatusCode,
         test::{self, TestRequest},
     };
 
@@ -237,4 +286,203 @@ mod tests {
                 .map(HeaderValue::as_bytes)
         );
     }
+
+    #[actix_rt::test]
+    async fn test_wildcard_origin_pattern() {
+        let cors = Cors::default()
+            .allowed_origin("https://*.example.com")
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::get()
+            .insert_header((header::ORIGIN, "https://foo.example.com"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(
+            Some(&b"https://foo.example.com"[..]),
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .map(HeaderValue::as_bytes)
+        );
+
+        let req = TestRequest::get()
+            .insert_header((header::ORIGIN, "https://evil.com"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[actix_rt::test]
+    async fn test_send_wildcard() {
+        let cors = Cors::default()
+            .allow_any_origin()
+            .send_wildcard()
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::get()
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(
+            Some(&b"*"[..]),
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .map(HeaderValue::as_bytes)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_send_wildcard_rejects_credentials() {
+        let result = Cors::default()
+            .allow_any_origin()
+            .send_wildcard()
+            .supports_credentials()
+            .new_transform(test::ok_service())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_preflight_vary_header() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .allowed_methods(vec!["GET"])
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(
+            Some(&b"Origin, Access-Control-Request-Method, Access-Control-Request-Headers"[..]),
+            res.headers().get(header::VARY).map(HeaderValue::as_bytes)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_preflight_vary_header_disabled() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .allowed_methods(vec!["GET"])
+            .disable_vary_header()
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(None, res.headers().get(header::VARY));
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_rejection_handler() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .rejection_handler(|_err, _req_head| HttpResponse::Forbidden().finish())
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::get()
+            .insert_header((header::ORIGIN, "https://evil.com"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::FORBIDDEN, res.status());
+    }
+
+    #[actix_rt::test]
+    async fn test_default_rejection_response() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::get()
+            .insert_header((header::ORIGIN, "https://evil.com"))
+            .to_srv_request();
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[actix_rt::test]
+    async fn test_override_widens_allowed_methods() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .allowed_methods(vec!["GET"])
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let over = Cors::default()
+            .allowed_methods(vec!["POST"])
+            .into_override()
+            .unwrap();
+
+        let mut req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_srv_request();
+        req.extensions_mut().insert(over);
+
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[actix_rt::test]
+    async fn test_override_not_touching_origins_keeps_parent_origin_restriction() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .allowed_methods(vec!["GET"])
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let over = Cors::default()
+            .allowed_methods(vec!["POST"])
+            .into_override()
+            .unwrap();
+
+        let mut req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://evil.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_srv_request();
+        req.extensions_mut().insert(over);
+
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[actix_rt::test]
+    async fn test_without_override_method_still_rejected() {
+        let cors = Cors::default()
+            .allowed_origin("https://example.com")
+            .allowed_methods(vec!["GET"])
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/")
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_srv_request();
+
+        let res = cors.call(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
 }
\ No newline at end of file